@@ -0,0 +1,569 @@
+//! The main client used to authenticate with Spotify and call the Web API.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use thiserror::Error;
+use url::Url;
+
+use crate::http::{BaseHttpClient, Form, Headers, HttpClient, RetryConfig};
+use crate::oauth2::{generate_code_challenge, generate_code_verifier, token_from_response, Credentials, OAuth, Token};
+
+/// Errors that can occur while making requests to Spotify.
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error("json parse error: {0}")]
+    ParseJson(#[from] serde_json::Error),
+    #[error("url parse error: {0}")]
+    ParseUrl(#[from] url::ParseError),
+    #[error("request error: {0}")]
+    Request(String),
+    #[error("status code {0}: {1}")]
+    StatusCode(u16, String),
+    #[error("missing access token")]
+    MissingToken,
+    #[error("missing refresh token")]
+    MissingRefreshToken,
+    #[error("no PKCE code_verifier to send; call `get_authorize_url` again first")]
+    MissingPkceVerifier,
+}
+
+impl From<std::io::Error> for ClientError {
+    fn from(err: std::io::Error) -> Self {
+        ClientError::Request(err.to_string())
+    }
+}
+
+pub type ClientResult<T> = Result<T, ClientError>;
+
+const SPOTIFY_AUTHORIZE_URL: &str = "https://accounts.spotify.com/authorize";
+const SPOTIFY_TOKEN_URL: &str = "https://accounts.spotify.com/api/token";
+
+/// The main client used to authenticate with Spotify and call the endpoints
+/// in [`crate::model`].
+#[derive(Clone, Debug)]
+pub struct Spotify {
+    pub creds: Credentials,
+    pub oauth: OAuth,
+    /// The token used to authenticate every endpoint call. Populated by
+    /// [`Spotify::request_user_token`]/[`Spotify::from_token`]; refresh it
+    /// with [`Spotify::refresh_user_token`] once [`Token::is_expired`].
+    pub token: Arc<Mutex<Option<Token>>>,
+    /// The HTTP client backing every request. Public so that callers can
+    /// tune its [`crate::http::RetryConfig`] (`spotify.http.retry = ...`)
+    /// without having to bypass `Spotify` to hand-build a client.
+    pub http: HttpClient,
+    /// Populated by [`Spotify::get_authorize_url`] when `creds` has no
+    /// secret, and consumed by the following [`Spotify::request_user_token`]
+    /// call to authenticate the token exchange via PKCE instead of a secret.
+    pkce_verifier: Arc<Mutex<Option<String>>>,
+}
+
+impl Spotify {
+    pub fn new(creds: Credentials, oauth: OAuth) -> Self {
+        Spotify {
+            creds,
+            oauth,
+            token: Arc::new(Mutex::new(None)),
+            http: HttpClient::default(),
+            pkce_verifier: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Like [`Spotify::new`], but with a custom [`RetryConfig`] for the
+    /// underlying HTTP client instead of its defaults.
+    pub fn new_with_retry_config(creds: Credentials, oauth: OAuth, retry: RetryConfig) -> Self {
+        let mut spotify = Self::new(creds, oauth);
+        spotify.http.retry = retry;
+        spotify
+    }
+
+    /// Builds a client directly from an access token obtained elsewhere (e.g.
+    /// another service's OAuth flow), skipping the authorize-URL/token
+    /// exchange dance entirely. `oauth` is left at its default since this
+    /// client will never need to request a token on its own; `creds` is
+    /// still required (and must be the app's real `client_id`, plus its
+    /// `secret` if it has one), since [`Spotify::refresh_user_token`] sends
+    /// it to Spotify's token endpoint. Pass a `token` with a `refresh_token` if you want
+    /// `refresh_user_token` to work later.
+    pub fn from_token(creds: Credentials, token: Token) -> Self {
+        Spotify {
+            creds,
+            oauth: OAuth::default(),
+            token: Arc::new(Mutex::new(Some(token))),
+            http: HttpClient::default(),
+            pkce_verifier: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Returns the URL needed to authorize the current client as the first
+    /// step of the Authorization Code flow.
+    ///
+    /// If `creds` was built with [`Credentials::new_pkce`], this also
+    /// generates the `code_verifier`/`code_challenge` pair required by PKCE,
+    /// stashes the verifier for the following [`Spotify::request_user_token`]
+    /// call, and appends `code_challenge`/`code_challenge_method=S256` to the
+    /// URL instead of relying on a client secret.
+    pub fn get_authorize_url(&self, show_dialog: bool) -> ClientResult<String> {
+        let scopes = self
+            .oauth
+            .scopes
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let mut url = Url::parse(SPOTIFY_AUTHORIZE_URL)?;
+        {
+            let mut params = url.query_pairs_mut();
+            params
+                .append_pair("client_id", &self.creds.id)
+                .append_pair("response_type", "code")
+                .append_pair("redirect_uri", &self.oauth.redirect_uri)
+                .append_pair("scope", &scopes)
+                .append_pair("state", &self.oauth.state);
+            if show_dialog {
+                params.append_pair("show_dialog", "true");
+            }
+
+            if self.creds.secret.is_none() {
+                let verifier = generate_code_verifier();
+                let challenge = generate_code_challenge(&verifier);
+                params
+                    .append_pair("code_challenge_method", "S256")
+                    .append_pair("code_challenge", &challenge);
+                *self.pkce_verifier.lock().unwrap() = Some(verifier);
+            }
+        }
+
+        Ok(url.into())
+    }
+
+    /// Parses the authorization code out of the redirect URI the user is sent
+    /// back to after visiting the URL from [`Spotify::get_authorize_url`].
+    pub fn parse_response_code(&self, url: &str) -> Option<String> {
+        let url = Url::parse(url).ok()?;
+        url.query_pairs()
+            .find(|(key, _)| key == "code")
+            .map(|(_, code)| code.into_owned())
+    }
+
+    /// Builds the form body and, when relevant, the `Authorization: Basic`
+    /// header for the token exchange following [`Spotify::parse_response_code`].
+    ///
+    /// When the client has no secret (PKCE), the verifier generated in
+    /// [`Spotify::get_authorize_url`] is sent as `code_verifier` and no
+    /// `Authorization` header is added; otherwise the client secret is sent
+    /// as a Basic auth header as usual. The verifier is only *read* here, not
+    /// consumed: it's removed by the caller once the exchange has actually
+    /// succeeded, so a failed attempt (e.g. a network blip) can be retried
+    /// without having to send the user through `get_authorize_url` again.
+    fn authorization_code_request(&self, code: &str) -> ClientResult<(HashMap<&'static str, String>, Option<Headers>)> {
+        let mut data = HashMap::new();
+        data.insert("grant_type", "authorization_code".to_owned());
+        data.insert("code", code.to_owned());
+        data.insert("redirect_uri", self.oauth.redirect_uri.clone());
+
+        if let Some(secret) = &self.creds.secret {
+            let auth = STANDARD.encode(format!("{}:{}", self.creds.id, secret));
+            let mut headers = Headers::new();
+            headers.insert("Authorization".to_owned(), format!("Basic {}", auth));
+            return Ok((data, Some(headers)));
+        }
+
+        let verifier = self
+            .pkce_verifier
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or(ClientError::MissingPkceVerifier)?;
+        data.insert("client_id", self.creds.id.clone());
+        data.insert("code_verifier", verifier);
+        Ok((data, None))
+    }
+
+    /// Exchanges the authorization code obtained via
+    /// [`Spotify::parse_response_code`] for an access token.
+    ///
+    /// When `creds` has no secret, this sends the `code_verifier` generated
+    /// by the previous [`Spotify::get_authorize_url`] call instead of an
+    /// `Authorization: Basic` header, following the PKCE extension to the
+    /// Authorization Code flow.
+    #[maybe_async::maybe_async]
+    pub async fn request_user_token(&self, code: &str) -> ClientResult<Token> {
+        let (data, headers) = self.authorization_code_request(code)?;
+        let payload = data
+            .iter()
+            .map(|(key, val)| (*key, val.as_str()))
+            .collect::<Form>();
+
+        let body = self.http.post_form(SPOTIFY_TOKEN_URL, headers.as_ref(), &payload).await?;
+        let token = token_from_response(&body)?;
+
+        // Only consumed once the exchange has actually succeeded; see the
+        // note on `authorization_code_request`.
+        self.pkce_verifier.lock().unwrap().take();
+        *self.token.lock().unwrap() = Some(token.clone());
+        Ok(token)
+    }
+
+    /// Uses the current token's `refresh_token` to obtain a new access
+    /// token, storing it back on `self.token`. Spotify's refresh tokens are
+    /// long-lived, so this is the cheap alternative to sending the user
+    /// through [`Spotify::get_authorize_url`] again once
+    /// [`Token::is_expired`].
+    #[maybe_async::maybe_async]
+    pub async fn refresh_user_token(&self) -> ClientResult<Token> {
+        let refresh_token = self
+            .token
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|token| token.refresh_token.clone())
+            .ok_or(ClientError::MissingRefreshToken)?;
+
+        let mut data = HashMap::new();
+        data.insert("grant_type", "refresh_token".to_owned());
+        data.insert("refresh_token", refresh_token.clone());
+
+        let headers = if let Some(secret) = &self.creds.secret {
+            let auth = STANDARD.encode(format!("{}:{}", self.creds.id, secret));
+            let mut headers = Headers::new();
+            headers.insert("Authorization".to_owned(), format!("Basic {}", auth));
+            Some(headers)
+        } else {
+            data.insert("client_id", self.creds.id.clone());
+            None
+        };
+
+        let payload = data
+            .iter()
+            .map(|(key, val)| (*key, val.as_str()))
+            .collect::<Form>();
+        let body = self.http.post_form(SPOTIFY_TOKEN_URL, headers.as_ref(), &payload).await?;
+        let mut token = token_from_response(&body)?;
+        // Spotify's refresh response may omit `refresh_token` when it hasn't
+        // changed; keep the previous one in that case.
+        if token.refresh_token.is_none() {
+            token.refresh_token = Some(refresh_token);
+        }
+        *self.token.lock().unwrap() = Some(token.clone());
+        Ok(token)
+    }
+
+    fn bearer_headers(&self) -> ClientResult<Headers> {
+        let token = self.token.lock().unwrap();
+        let access_token = token.as_ref().ok_or(ClientError::MissingToken)?.access_token.clone();
+        let mut headers = Headers::new();
+        headers.insert("Authorization".to_owned(), format!("Bearer {}", access_token));
+        Ok(headers)
+    }
+
+    /// Saves one or more tracks to the current user's library.
+    ///
+    /// Spotify caps this endpoint at 50 IDs per request, so `track_ids` is
+    /// transparently split into chunks of that size; see
+    /// [`Spotify::batched_put`] for how the chunked requests are issued and
+    /// merged back together.
+    #[maybe_async::maybe_async]
+    pub async fn save_tracks(&self, track_ids: &[&str]) -> ClientResult<()> {
+        self.batched_put("https://api.spotify.com/v1/me/tracks", track_ids, TRACK_ID_CHUNK_SIZE)
+            .await
+    }
+
+    /// Saves one or more albums to the current user's library.
+    ///
+    /// Spotify caps this endpoint at 20 IDs per request, so `album_ids` is
+    /// transparently split into chunks of that size; see
+    /// [`Spotify::batched_put`] for how the chunked requests are issued and
+    /// merged back together.
+    #[maybe_async::maybe_async]
+    pub async fn save_albums(&self, album_ids: &[&str]) -> ClientResult<()> {
+        self.batched_put("https://api.spotify.com/v1/me/albums", album_ids, ALBUM_ID_CHUNK_SIZE)
+            .await
+    }
+
+    /// Checks whether each of `track_ids` is already saved to the current
+    /// user's library, in the same order they were given.
+    ///
+    /// Spotify caps this endpoint at 50 IDs per request, so `track_ids` is
+    /// transparently split into chunks of that size; see
+    /// [`Spotify::batched_get`] for how the chunked requests are issued and
+    /// the per-chunk `Vec<bool>` responses are reassembled in order.
+    #[maybe_async::maybe_async]
+    pub async fn check_saved_tracks(&self, track_ids: &[&str]) -> ClientResult<Vec<bool>> {
+        self.batched_get("https://api.spotify.com/v1/me/tracks/contains", track_ids, TRACK_ID_CHUNK_SIZE)
+            .await
+    }
+
+    /// Shared batching helper for read endpoints with a per-request ID limit:
+    /// splits `ids` into `chunk_size`-sized groups, GETs each group's `ids`
+    /// query parameter from `url`, and flattens the chunks' `Vec<bool>`
+    /// responses back into a single one, preserving the original order of
+    /// `ids` regardless of which chunk finishes first.
+    ///
+    /// In async builds the chunks are dispatched concurrently (bounded by
+    /// [`MAX_CONCURRENT_CHUNKS`]) via `StreamExt::buffered`, which polls them
+    /// out-of-order but always yields their outputs back in submission
+    /// order; the ureq/sync client issues them one after another.
+    #[cfg(not(feature = "is_sync"))]
+    async fn batched_get(&self, url: &str, ids: &[&str], chunk_size: usize) -> ClientResult<Vec<bool>> {
+        let headers = self.bearer_headers()?;
+        batched_get_with(&self.http, url, ids, chunk_size, &headers).await
+    }
+
+    #[cfg(feature = "is_sync")]
+    fn batched_get(&self, url: &str, ids: &[&str], chunk_size: usize) -> ClientResult<Vec<bool>> {
+        let headers = self.bearer_headers()?;
+        batched_get_with(&self.http, url, ids, chunk_size, &headers)
+    }
+
+    #[maybe_async::maybe_async]
+    async fn get_ids_chunk(&self, url: &str, chunk: &[&str], headers: &Headers) -> ClientResult<Vec<bool>> {
+        get_ids_chunk_with(&self.http, url, chunk, headers).await
+    }
+
+    /// Shared batching helper for write endpoints with a per-request ID
+    /// limit: splits `ids` into `chunk_size`-sized groups and PUTs each
+    /// group's `ids` query parameter to `url`, so callers can pass
+    /// arbitrarily long ID lists without hand-rolling the chunking
+    /// themselves.
+    ///
+    /// In async builds the chunks are dispatched concurrently (bounded by
+    /// [`MAX_CONCURRENT_CHUNKS`]); the ureq/sync client issues them one after
+    /// another, since it has no concurrency to bound.
+    #[cfg(not(feature = "is_sync"))]
+    async fn batched_put(&self, url: &str, ids: &[&str], chunk_size: usize) -> ClientResult<()> {
+        use futures::stream::{self, StreamExt, TryStreamExt};
+
+        let headers = self.bearer_headers()?;
+        stream::iter(ids.chunks(chunk_size))
+            .map(|chunk| self.put_ids_chunk(url, chunk, &headers))
+            .buffer_unordered(MAX_CONCURRENT_CHUNKS)
+            .try_collect::<Vec<_>>()
+            .await?;
+        Ok(())
+    }
+
+    #[cfg(feature = "is_sync")]
+    fn batched_put(&self, url: &str, ids: &[&str], chunk_size: usize) -> ClientResult<()> {
+        let headers = self.bearer_headers()?;
+        for chunk in ids.chunks(chunk_size) {
+            self.put_ids_chunk(url, chunk, &headers)?;
+        }
+        Ok(())
+    }
+
+    #[maybe_async::maybe_async]
+    async fn put_ids_chunk(&self, url: &str, chunk: &[&str], headers: &Headers) -> ClientResult<String> {
+        let payload = serde_json::json!({ "ids": chunk });
+        self.http.put(url, Some(headers), &payload).await
+    }
+}
+
+/// Generic version of [`Spotify::get_ids_chunk`], taking any [`BaseHttpClient`]
+/// instead of reading `self.http`, so [`batched_get_with`] (and its tests) can
+/// be exercised against a fake client.
+#[maybe_async::maybe_async]
+async fn get_ids_chunk_with<C: BaseHttpClient>(
+    http: &C,
+    url: &str,
+    chunk: &[&str],
+    headers: &Headers,
+) -> ClientResult<Vec<bool>> {
+    let query = [("ids", chunk.join(","))];
+    let query = query.iter().map(|(k, v)| (*k, v.as_str())).collect();
+    let body = http.get(url, Some(headers), &query).await?;
+    Ok(serde_json::from_str(&body)?)
+}
+
+/// Generic version of [`Spotify::batched_get`]; see its docs for the
+/// reassembly guarantee this is responsible for.
+#[cfg(not(feature = "is_sync"))]
+async fn batched_get_with<C: BaseHttpClient>(
+    http: &C,
+    url: &str,
+    ids: &[&str],
+    chunk_size: usize,
+    headers: &Headers,
+) -> ClientResult<Vec<bool>> {
+    use futures::stream::{self, StreamExt, TryStreamExt};
+
+    let mut pending = stream::iter(ids.chunks(chunk_size))
+        .map(|chunk| get_ids_chunk_with(http, url, chunk, headers))
+        .buffered(MAX_CONCURRENT_CHUNKS);
+
+    let mut results = Vec::with_capacity(ids.len());
+    while let Some(chunk) = pending.try_next().await? {
+        results.extend(chunk);
+    }
+    Ok(results)
+}
+
+#[cfg(feature = "is_sync")]
+fn batched_get_with<C: BaseHttpClient>(
+    http: &C,
+    url: &str,
+    ids: &[&str],
+    chunk_size: usize,
+    headers: &Headers,
+) -> ClientResult<Vec<bool>> {
+    let mut results = Vec::with_capacity(ids.len());
+    for chunk in ids.chunks(chunk_size) {
+        results.extend(get_ids_chunk_with(http, url, chunk, headers)?);
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::Query;
+    use serde_json::Value;
+
+    fn pkce_spotify() -> Spotify {
+        Spotify::new(
+            Credentials::new_pkce("client-id"),
+            OAuth {
+                redirect_uri: "http://localhost:8888/callback".to_owned(),
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn authorization_code_request_errors_without_a_prior_authorize_url_call() {
+        let spotify = pkce_spotify();
+        let err = spotify.authorization_code_request("some-code").unwrap_err();
+        assert!(matches!(err, ClientError::MissingPkceVerifier));
+    }
+
+    #[test]
+    fn authorization_code_request_does_not_consume_the_verifier() {
+        let spotify = pkce_spotify();
+        spotify.get_authorize_url(false).unwrap();
+
+        // Simulates a failed exchange attempt followed by a retry: both
+        // calls must see the same verifier instead of the first one
+        // deleting it.
+        let (first, _) = spotify.authorization_code_request("some-code").unwrap();
+        let (second, _) = spotify.authorization_code_request("some-code").unwrap();
+        assert_eq!(first.get("code_verifier"), second.get("code_verifier"));
+        assert!(first.get("code_verifier").is_some());
+    }
+
+    #[test]
+    fn chunks_respect_the_requested_chunk_size_at_the_boundary() {
+        let ids: Vec<&str> = (0..TRACK_ID_CHUNK_SIZE + 1).map(|_| "id").collect();
+        let chunked = ids.chunks(TRACK_ID_CHUNK_SIZE).collect::<Vec<_>>();
+        assert_eq!(chunked.len(), 2);
+        assert_eq!(chunked[0].len(), TRACK_ID_CHUNK_SIZE);
+        assert_eq!(chunked[1].len(), 1);
+    }
+
+    #[test]
+    fn new_with_retry_config_threads_the_given_config_through() {
+        let retry = RetryConfig {
+            max_retries: 7,
+            max_backoff: std::time::Duration::from_secs(1),
+        };
+        let spotify = Spotify::new_with_retry_config(
+            Credentials::new_pkce("client-id"),
+            OAuth {
+                redirect_uri: "http://localhost:8888/callback".to_owned(),
+                ..Default::default()
+            },
+            retry,
+        );
+        assert_eq!(spotify.http.retry.max_retries, 7);
+        assert_eq!(spotify.http.retry.max_backoff, std::time::Duration::from_secs(1));
+    }
+
+    /// A [`Future`] that stays `Pending` for `polls_remaining` polls before
+    /// resolving, so tests can make chunks complete in a chosen order instead
+    /// of however fast the real IO happens to land.
+    #[cfg(not(feature = "is_sync"))]
+    struct DelayedReady(usize);
+
+    #[cfg(not(feature = "is_sync"))]
+    impl std::future::Future for DelayedReady {
+        type Output = ();
+
+        fn poll(
+            mut self: std::pin::Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<()> {
+            if self.0 == 0 {
+                std::task::Poll::Ready(())
+            } else {
+                self.0 -= 1;
+                cx.waker().wake_by_ref();
+                std::task::Poll::Pending
+            }
+        }
+    }
+
+    /// A [`BaseHttpClient`] double for [`get_ids_chunk_with`]/
+    /// [`batched_get_with`]: every "ids" query is a single `id-<n>` id, whose
+    /// response is `[n is odd]` and which resolves after `n` extra polls, so
+    /// chunks submitted first are made to complete *last*.
+    #[cfg(not(feature = "is_sync"))]
+    #[derive(Debug, Clone, Default)]
+    struct FakeHttpClient;
+
+    #[cfg(not(feature = "is_sync"))]
+    #[maybe_async::async_impl]
+    impl BaseHttpClient for FakeHttpClient {
+        async fn get(&self, _url: &str, _headers: Option<&Headers>, payload: &Query) -> ClientResult<String> {
+            let id = *payload.get("ids").expect("a single id per chunk");
+            let n: usize = id.rsplit('-').next().unwrap().parse().unwrap();
+            DelayedReady(n).await;
+            Ok(serde_json::json!([n % 2 == 1]).to_string())
+        }
+
+        async fn post(&self, _url: &str, _headers: Option<&Headers>, _payload: &Value) -> ClientResult<String> {
+            unimplemented!("unused by these tests")
+        }
+
+        async fn post_form<'a>(
+            &self,
+            _url: &str,
+            _headers: Option<&Headers>,
+            _payload: &Form<'a>,
+        ) -> ClientResult<String> {
+            unimplemented!("unused by these tests")
+        }
+
+        async fn put(&self, _url: &str, _headers: Option<&Headers>, _payload: &Value) -> ClientResult<String> {
+            unimplemented!("unused by these tests")
+        }
+
+        async fn delete(&self, _url: &str, _headers: Option<&Headers>, _payload: &Value) -> ClientResult<String> {
+            unimplemented!("unused by these tests")
+        }
+    }
+
+    #[cfg(not(feature = "is_sync"))]
+    #[test]
+    fn batched_get_reassembles_chunks_in_submission_order_not_completion_order() {
+        // "id-3" is submitted first but, thanks to `DelayedReady`, resolves
+        // last; if `batched_get_with` reassembled by completion order
+        // instead of submission order, this would come back reversed.
+        let ids = ["id-3", "id-2", "id-1", "id-0"];
+        let http = FakeHttpClient::default();
+        let headers = Headers::new();
+
+        let results =
+            futures::executor::block_on(batched_get_with(&http, "https://example.invalid", &ids, 1, &headers))
+                .unwrap();
+
+        assert_eq!(results, vec![true, false, true, false]);
+    }
+}
+
+/// Maximum number of ID-chunk requests dispatched at once by
+/// [`Spotify::batched_put`]/[`Spotify::batched_get`] in async builds.
+const MAX_CONCURRENT_CHUNKS: usize = 5;
+const TRACK_ID_CHUNK_SIZE: usize = 50;
+const ALBUM_ID_CHUNK_SIZE: usize = 20;
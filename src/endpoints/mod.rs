@@ -0,0 +1,4 @@
+//! Web API endpoint helpers that don't belong to a single client method, such
+//! as automatic pagination.
+
+pub mod pagination;
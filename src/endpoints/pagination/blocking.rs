@@ -0,0 +1,82 @@
+//! Blocking implementation of automatic pagination requests, used by the
+//! synchronous ureq client.
+
+use crate::model::Page;
+use crate::ClientResult;
+
+/// Alias for a blocking `Iterator<Item = ClientResult<T>>`, since sync mode
+/// is enabled.
+pub type Paginator<'a, T> = Box<dyn Iterator<Item = ClientResult<T>> + 'a>;
+
+/// This is used to handle paginated requests automatically. Mirrors the
+/// offset-advancing, `next`-terminating logic of the async `paginate`, but as
+/// a plain blocking iterator instead of a `Stream`.
+pub fn paginate<'a, T, Request>(req: Request, page_size: u32) -> Paginator<'a, T>
+where
+    T: 'a,
+    Request: Fn(u32, u32) -> ClientResult<Page<T>> + 'a,
+{
+    let mut offset = 0;
+    let mut buffer = std::collections::VecDeque::new();
+    let mut done = false;
+
+    Box::new(std::iter::from_fn(move || {
+        // A page can legitimately come back empty without being the last one
+        // (e.g. a deleted item shifted everything by one); keep fetching
+        // until we have something to yield or really are done, otherwise a
+        // `for`/`.collect()` would stop at the first `None` and silently
+        // truncate the results.
+        while buffer.is_empty() && !done {
+            match req(page_size, offset) {
+                Ok(page) => {
+                    offset += page.items.len() as u32;
+                    done = page.next.is_none();
+                    buffer.extend(page.items.into_iter().map(Ok));
+                }
+                Err(err) => {
+                    done = true;
+                    buffer.push_back(Err(err));
+                }
+            }
+        }
+
+        buffer.pop_front()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(items: Vec<u32>, next: Option<&str>) -> Page<u32> {
+        Page {
+            href: String::new(),
+            limit: items.len() as u32,
+            offset: 0,
+            previous: None,
+            total: items.len() as u32,
+            next: next.map(str::to_owned),
+            items,
+        }
+    }
+
+    #[test]
+    fn paginate_keeps_going_past_an_empty_non_final_page() {
+        let pages = std::cell::RefCell::new(
+            vec![
+                page(vec![1, 2], Some("next")),
+                // An empty page that isn't the last one must not be mistaken
+                // for the end of iteration.
+                page(vec![], Some("next")),
+                page(vec![3], None),
+            ]
+            .into_iter(),
+        );
+
+        let items = paginate(move |_limit, _offset| Ok(pages.borrow_mut().next().unwrap()), 2)
+            .collect::<ClientResult<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(items, vec![1, 2, 3]);
+    }
+}
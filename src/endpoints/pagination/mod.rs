@@ -0,0 +1,13 @@
+//! Automatic pagination for endpoints that return a [`crate::model::Page`],
+//! gated by `maybe_async` between an async `Stream` and a blocking
+//! `Iterator` depending on which HTTP client is enabled.
+
+#[cfg(feature = "is_sync")]
+mod blocking;
+#[cfg(not(feature = "is_sync"))]
+mod stream;
+
+#[cfg(feature = "is_sync")]
+pub use blocking::{paginate, Paginator};
+#[cfg(not(feature = "is_sync"))]
+pub use stream::{paginate, paginate_concurrent, Paginator};
@@ -1,5 +1,7 @@
 //! Asynchronous implementation of automatic pagination requests.
 
+use std::pin::Pin;
+
 use crate::model::Page;
 use crate::ClientResult;
 use futures::future::Future;
@@ -30,3 +32,94 @@ where
         }
     })
 }
+
+/// Like [`paginate`], but once the first page reveals [`Page::total`], the
+/// remaining `(limit, offset)` windows are computed up front and driven up to
+/// `max_in_flight` requests at a time, instead of awaiting each page before
+/// requesting the next one. Items are still yielded in order.
+///
+/// This only works for endpoints that report `total`; cursor-based endpoints
+/// that don't should keep using [`paginate`].
+pub fn paginate_concurrent<'a, T, Fut, Request>(
+    req: Request,
+    page_size: u32,
+    max_in_flight: usize,
+) -> Paginator<'a, ClientResult<T>>
+where
+    T: Unpin + 'a,
+    Fut: Future<Output = ClientResult<Page<T>>> + 'a,
+    Request: Fn(u32, u32) -> Fut + 'a,
+{
+    use async_stream::stream;
+    use futures::stream::FuturesOrdered;
+    use futures::StreamExt;
+
+    Box::pin(stream! {
+        let first = req(page_size, 0).await?;
+        let total = first.total;
+        for item in first.items {
+            yield Ok(item);
+        }
+
+        let mut offset = page_size;
+        let mut pending = FuturesOrdered::new();
+        while offset < total || !pending.is_empty() {
+            while offset < total && pending.len() < max_in_flight {
+                pending.push_back(req(page_size, offset));
+                offset += page_size;
+            }
+
+            match pending.next().await {
+                Some(page) => {
+                    for item in page?.items {
+                        yield Ok(item);
+                    }
+                }
+                None => break,
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use futures::future::ready;
+    use futures::StreamExt;
+
+    fn page(total: u32, offset: u32, page_size: u32) -> Page<u32> {
+        let items = (offset..(offset + page_size).min(total)).collect::<Vec<_>>();
+        Page {
+            href: String::new(),
+            limit: page_size,
+            offset,
+            previous: None,
+            total,
+            next: if offset + page_size < total {
+                Some("next".to_owned())
+            } else {
+                None
+            },
+            items,
+        }
+    }
+
+    #[test]
+    fn paginate_concurrent_yields_every_item_in_order() {
+        let total = 10;
+        let page_size = 3;
+        let paginator = paginate_concurrent(
+            move |limit, offset| ready(Ok(page(total, offset, limit))),
+            page_size,
+            4,
+        );
+
+        let items = block_on(paginator.collect::<Vec<_>>())
+            .into_iter()
+            .collect::<ClientResult<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(items, (0..total).collect::<Vec<_>>());
+    }
+}
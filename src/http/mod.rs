@@ -0,0 +1,71 @@
+//! Base HTTP client trait and type aliases, plus the concrete
+//! implementations for each supported HTTP backend.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use maybe_async::maybe_async;
+use serde_json::Value;
+
+use crate::client::ClientResult;
+
+#[cfg(feature = "client-reqwest")]
+pub mod reqwest;
+#[cfg(feature = "client-ureq")]
+pub mod ureq;
+
+#[cfg(feature = "client-reqwest")]
+pub use self::reqwest::ReqwestClient;
+#[cfg(feature = "client-ureq")]
+pub use self::ureq::UreqClient;
+
+/// The concrete [`BaseHttpClient`] backing [`crate::client::Spotify`], chosen
+/// by whichever `client-*` feature is enabled. `Spotify` holds one of these
+/// rather than constructing a fresh default client per request, so that a
+/// caller-set [`RetryConfig`] is actually honored.
+#[cfg(feature = "client-reqwest")]
+pub type HttpClient = ReqwestClient;
+#[cfg(feature = "client-ureq")]
+pub type HttpClient = UreqClient;
+
+pub type Headers = HashMap<String, String>;
+pub type Query<'a> = HashMap<&'a str, &'a str>;
+pub type Form<'a> = HashMap<&'a str, &'a str>;
+
+/// Configuration for the retry layer each HTTP backend applies to every
+/// request: on a 429 the `Retry-After` header is honored as-is, and on a 5xx
+/// capped exponential backoff is used instead, since those responses don't
+/// carry a wait hint.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryConfig {
+    /// Maximum number of retries before giving up and returning the error.
+    pub max_retries: u32,
+    /// Upper bound for the exponential backoff applied to 5xx responses.
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        RetryConfig {
+            max_retries: 3,
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+/// This trait represents the interface required to make requests. It lets us
+/// implement queries in [`crate::client`] a single time, and have them work
+/// the same way no matter which HTTP backend is enabled underneath.
+#[maybe_async]
+pub trait BaseHttpClient: Send + Default + Clone + std::fmt::Debug {
+    async fn get(&self, url: &str, headers: Option<&Headers>, payload: &Query) -> ClientResult<String>;
+    async fn post(&self, url: &str, headers: Option<&Headers>, payload: &Value) -> ClientResult<String>;
+    async fn post_form<'a>(
+        &self,
+        url: &str,
+        headers: Option<&Headers>,
+        payload: &Form<'a>,
+    ) -> ClientResult<String>;
+    async fn put(&self, url: &str, headers: Option<&Headers>, payload: &Value) -> ClientResult<String>;
+    async fn delete(&self, url: &str, headers: Option<&Headers>, payload: &Value) -> ClientResult<String>;
+}
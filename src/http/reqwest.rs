@@ -0,0 +1,114 @@
+//! The client implementation for the reqwest HTTP client, which is
+//! asynchronous.
+
+use std::time::Duration;
+
+use super::{BaseHttpClient, Form, Headers, Query, RetryConfig};
+use crate::client::{ClientError, ClientResult};
+
+use maybe_async::async_impl;
+use reqwest::{Method, RequestBuilder};
+use serde_json::Value;
+
+impl ClientError {
+    pub fn from_response(r: reqwest::Response) -> Self {
+        ClientError::StatusCode(r.status().as_u16(), r.status().to_string())
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct ReqwestClient {
+    pub retry: RetryConfig,
+    client: reqwest::Client,
+}
+
+impl ReqwestClient {
+    /// Builds the request the same way every time so that it can be reissued
+    /// on a retry; see the module-level request handling in [`Self::request`].
+    fn build(&self, method: Method, url: &str, headers: Option<&Headers>) -> RequestBuilder {
+        let mut builder = self.client.request(method, url);
+        if let Some(headers) = headers {
+            for (key, val) in headers.iter() {
+                builder = builder.header(key, val);
+            }
+        }
+        builder
+    }
+
+    /// On a 429 the `Retry-After` header is honored as-is, and on a 5xx
+    /// capped exponential backoff is used instead, up to
+    /// `self.retry.max_retries` times; see [`RetryConfig`].
+    async fn request<F>(&self, build: F) -> ClientResult<String>
+    where
+        F: Fn() -> RequestBuilder,
+    {
+        let mut attempt = 0;
+        loop {
+            let response = build()
+                .send()
+                .await
+                .map_err(|err| ClientError::Request(err.to_string()))?;
+
+            let status = response.status();
+            if status.is_success() {
+                return response
+                    .text()
+                    .await
+                    .map_err(|err| ClientError::Request(err.to_string()));
+            }
+
+            if attempt < self.retry.max_retries && (status.as_u16() == 429 || status.is_server_error()) {
+                let wait = if status.as_u16() == 429 {
+                    response
+                        .headers()
+                        .get("Retry-After")
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(|v| v.parse().ok())
+                        .map(Duration::from_secs)
+                        .unwrap_or(self.retry.max_backoff)
+                } else {
+                    Duration::from_secs(1u64.checked_shl(attempt).unwrap_or(u64::MAX))
+                        .min(self.retry.max_backoff)
+                };
+                attempt += 1;
+                tokio::time::sleep(wait).await;
+                continue;
+            }
+
+            return Err(ClientError::from_response(response));
+        }
+    }
+}
+
+#[async_impl]
+impl BaseHttpClient for ReqwestClient {
+    async fn get(&self, url: &str, headers: Option<&Headers>, payload: &Query) -> ClientResult<String> {
+        self.request(|| self.build(Method::GET, url, headers).query(payload))
+            .await
+    }
+
+    async fn post(&self, url: &str, headers: Option<&Headers>, payload: &Value) -> ClientResult<String> {
+        self.request(|| self.build(Method::POST, url, headers).json(payload))
+            .await
+    }
+
+    async fn post_form<'a>(
+        &self,
+        url: &str,
+        headers: Option<&Headers>,
+        payload: &Form<'a>,
+    ) -> ClientResult<String> {
+        self.request(|| self.build(Method::POST, url, headers).form(payload))
+            .await
+    }
+
+    async fn put(&self, url: &str, headers: Option<&Headers>, payload: &Value) -> ClientResult<String> {
+        self.request(|| self.build(Method::PUT, url, headers).json(payload))
+            .await
+    }
+
+    async fn delete(&self, url: &str, headers: Option<&Headers>, payload: &Value) -> ClientResult<String> {
+        self.request(|| self.build(Method::DELETE, url, headers).json(payload))
+            .await
+    }
+}
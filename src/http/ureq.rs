@@ -1,6 +1,9 @@
 //! The client implementation for the ureq HTTP client, which is blocking.
 
-use super::{BaseHttpClient, Form, Headers, Query};
+use std::thread;
+use std::time::Duration;
+
+use super::{BaseHttpClient, Form, Headers, Query, RetryConfig};
 use crate::client::{ClientError, ClientResult};
 
 use maybe_async::sync_impl;
@@ -14,7 +17,9 @@ impl ClientError {
 }
 
 #[derive(Default, Debug, Clone)]
-pub struct UreqClient {}
+pub struct UreqClient {
+    pub retry: RetryConfig,
+}
 
 impl UreqClient {
     /// The request handling in ureq is split in three parts:
@@ -25,30 +30,55 @@ impl UreqClient {
     ///   for all requests.
     /// * The request is finished and performed with the `send_request` function
     ///   (JSON, a form...).
+    ///
+    /// A 429 response is retried after sleeping for its `Retry-After` header,
+    /// and a 5xx response is retried with capped exponential backoff, up to
+    /// `self.retry.max_retries` times; see [`RetryConfig`].
     fn request<D>(
         &self,
-        mut request: Request,
+        request: Request,
         headers: Option<&Headers>,
         send_request: D,
     ) -> ClientResult<String>
     where
         D: Fn(Request) -> Result<Response, ureq::Error>,
     {
-        // Setting the headers, which will be the token auth if unspecified.
-        if let Some(headers) = headers {
-            for (key, val) in headers.iter() {
-                request = request.set(&key, &val);
+        let mut attempt = 0;
+        loop {
+            let mut request = request.clone();
+            if let Some(headers) = headers {
+                for (key, val) in headers.iter() {
+                    request = request.set(key, val);
+                }
             }
-        }
 
-        log::info!("Making request {:?}", request);
-        match send_request(request) {
-            // Successful request
-            Ok(response) => response.into_string().map_err(Into::into),
-            // HTTP status error
-            Err(ureq::Error::Status(_, response)) => Err(ClientError::from_response(response)),
-            // Some kind of IO/transport error
-            Err(err) => Err(ClientError::Request(err.to_string())),
+            log::info!("Making request {:?}", request);
+            match send_request(request) {
+                // Successful request
+                Ok(response) => return response.into_string().map_err(Into::into),
+                // Rate limited: honor `Retry-After` if we still have retries left.
+                Err(ureq::Error::Status(429, response)) if attempt < self.retry.max_retries => {
+                    let wait = response
+                        .header("Retry-After")
+                        .and_then(|secs| secs.parse().ok())
+                        .map(Duration::from_secs)
+                        .unwrap_or(self.retry.max_backoff);
+                    attempt += 1;
+                    thread::sleep(wait);
+                }
+                // Transient server error: capped exponential backoff.
+                Err(ureq::Error::Status(status, response)) if status >= 500 && attempt < self.retry.max_retries => {
+                    let backoff = Duration::from_secs(1u64.checked_shl(attempt).unwrap_or(u64::MAX))
+                        .min(self.retry.max_backoff);
+                    attempt += 1;
+                    let _ = response;
+                    thread::sleep(backoff);
+                }
+                // HTTP status error, retries exhausted
+                Err(ureq::Error::Status(_, response)) => return Err(ClientError::from_response(response)),
+                // Some kind of IO/transport error
+                Err(err) => return Err(ClientError::Request(err.to_string())),
+            }
         }
     }
 }
@@ -162,12 +162,13 @@
 // confusing errors..
 
 pub mod client;
+pub mod endpoints;
+pub mod http;
 pub mod oauth2;
 
 // Subcrate re-exports
 pub use rspotify_macros as macros;
 pub use rspotify_model as model;
-pub use rspotify_http as http;
 
 // Top-level re-exports
 pub use macros::scopes;
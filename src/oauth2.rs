@@ -0,0 +1,166 @@
+//! All objects related to authenticating with the Spotify API, following the
+//! Authorization Code flow (with optional PKCE).
+
+use std::collections::HashSet;
+use std::env;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::{DateTime, Duration, Utc};
+use derive_builder::Builder;
+use rand::Rng;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+/// Spotify application credentials, as obtained from the [developer
+/// dashboard](https://developer.spotify.com/dashboard/applications).
+///
+/// `secret` is optional: apps that can't safely hold on to a secret (CLI or
+/// desktop apps) should use [`Credentials::new_pkce`] instead of
+/// [`Credentials::new`], which authenticates the Authorization Code flow with
+/// PKCE rather than a client secret.
+#[derive(Builder, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Credentials {
+    pub id: String,
+    #[builder(setter(strip_option), default)]
+    pub secret: Option<String>,
+}
+
+impl Credentials {
+    /// Builds the credentials for a confidential client, which authenticates
+    /// the token exchange with a client secret.
+    pub fn new(id: &str, secret: &str) -> Self {
+        Credentials {
+            id: id.to_owned(),
+            secret: Some(secret.to_owned()),
+        }
+    }
+
+    /// Builds the credentials for a public client with no secret, which
+    /// authenticates the token exchange with PKCE instead.
+    pub fn new_pkce(id: &str) -> Self {
+        Credentials {
+            id: id.to_owned(),
+            secret: None,
+        }
+    }
+
+    /// Parses `RSPOTIFY_CLIENT_ID`/`RSPOTIFY_CLIENT_SECRET` from the
+    /// environment (see the `env-file` feature to also load a `.env` file).
+    pub fn from_env() -> Option<Self> {
+        Some(Credentials {
+            id: env::var("RSPOTIFY_CLIENT_ID").ok()?,
+            secret: env::var("RSPOTIFY_CLIENT_SECRET").ok(),
+        })
+    }
+}
+
+/// Structure that holds the required per-request information for the
+/// Authorization Code flow, such as the redirect URI and the requested
+/// scopes.
+#[derive(Builder, Debug, Clone, Default, PartialEq, Eq)]
+pub struct OAuth {
+    pub redirect_uri: String,
+    #[builder(default)]
+    pub state: String,
+    #[builder(default)]
+    pub scopes: HashSet<String>,
+}
+
+/// An access token obtained from Spotify, along with its expiration and the
+/// refresh token needed to renew it.
+#[derive(Builder, Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub access_token: String,
+    pub expires_in: Duration,
+    pub expires_at: Option<DateTime<Utc>>,
+    #[builder(default)]
+    pub scopes: HashSet<String>,
+    #[builder(setter(strip_option), default)]
+    pub refresh_token: Option<String>,
+}
+
+impl Token {
+    /// Whether the token has already expired.
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .map(|expiration| Utc::now() >= expiration)
+            .unwrap_or(true)
+    }
+}
+
+/// The JSON body returned by Spotify's token endpoint.
+#[derive(Deserialize)]
+pub(crate) struct TokenPayload {
+    access_token: String,
+    expires_in: i64,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    #[serde(default)]
+    scope: String,
+}
+
+impl From<TokenPayload> for Token {
+    fn from(payload: TokenPayload) -> Self {
+        let expires_in = Duration::seconds(payload.expires_in);
+        Token {
+            access_token: payload.access_token,
+            expires_at: Some(Utc::now() + expires_in),
+            expires_in,
+            scopes: payload.scope.split_whitespace().map(str::to_owned).collect(),
+            refresh_token: payload.refresh_token,
+        }
+    }
+}
+
+/// Parses the body returned by Spotify's token endpoint into a [`Token`].
+pub(crate) fn token_from_response(body: &str) -> serde_json::Result<Token> {
+    serde_json::from_str::<TokenPayload>(body).map(Into::into)
+}
+
+/// Length of the `code_verifier` generated for PKCE, within the 43-128
+/// range allowed by [RFC 7636
+/// ](https://datatracker.ietf.org/doc/html/rfc7636#section-4.1).
+const PKCE_VERIFIER_LENGTH: usize = 128;
+
+/// The `unreserved` character set from [RFC 7636
+/// ](https://datatracker.ietf.org/doc/html/rfc7636#section-4.1): `A-Z`,
+/// `a-z`, `0-9`, `-`, `.`, `_`, `~`.
+const PKCE_UNRESERVED_CHARS: &[u8] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-._~";
+
+/// Generates a cryptographically random `code_verifier` for PKCE, made up of
+/// unreserved characters (`A-Z`, `a-z`, `0-9`, `-`, `.`, `_`, `~`).
+pub(crate) fn generate_code_verifier() -> String {
+    let mut rng = rand::thread_rng();
+    (0..PKCE_VERIFIER_LENGTH)
+        .map(|_| PKCE_UNRESERVED_CHARS[rng.gen_range(0..PKCE_UNRESERVED_CHARS.len())] as char)
+        .collect()
+}
+
+/// Derives the `code_challenge` sent in the authorize URL from a
+/// `code_verifier`, as `BASE64URL-ENCODE(SHA256(code_verifier))` with no
+/// padding, per the `S256` method in RFC 7636.
+pub(crate) fn generate_code_challenge(verifier: &str) -> String {
+    let digest = Sha256::digest(verifier.as_bytes());
+    URL_SAFE_NO_PAD.encode(digest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_challenge_matches_rfc_7636_appendix_b_vector() {
+        // https://datatracker.ietf.org/doc/html/rfc7636#appendix-B
+        let verifier = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+        let challenge = generate_code_challenge(verifier);
+        assert_eq!(challenge, "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM");
+    }
+
+    #[test]
+    fn code_verifier_is_the_right_length_and_charset() {
+        let verifier = generate_code_verifier();
+        assert_eq!(verifier.len(), PKCE_VERIFIER_LENGTH);
+        assert!(verifier.bytes().all(|b| PKCE_UNRESERVED_CHARS.contains(&b)));
+    }
+}